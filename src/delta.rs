@@ -0,0 +1,118 @@
+//! Inter-frame delta optimization for GIF export.
+//!
+//! Writing every frame as a full-canvas `Keep` write makes exported GIFs far
+//! larger than the source. This finds the tight bounding box of pixels that
+//! actually changed since the previous frame, so only that sub-rectangle is
+//! encoded, with unchanged pixels marked transparent.
+
+/// The changed region between two composited RGBA canvases, in pixels.
+pub(crate) struct ChangedRegion {
+    pub left: usize,
+    pub top: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Finds the tight bounding box of pixels that differ between `prev` and
+/// `curr`. Returns `None` if the two frames are pixel-identical.
+pub(crate) fn changed_bounds(
+    prev: &[u8],
+    curr: &[u8],
+    width: usize,
+    height: usize,
+) -> Option<ChangedRegion> {
+    let (mut min_x, mut min_y) = (width, height);
+    let (mut max_x, mut max_y) = (0usize, 0usize);
+    let mut any = false;
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) * 4;
+            if prev[i..i + 4] != curr[i..i + 4] {
+                any = true;
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    if !any {
+        return None;
+    }
+
+    Some(ChangedRegion {
+        left: min_x,
+        top: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    })
+}
+
+/// Extracts `region` from `curr`, marking any pixel unchanged from `prev` as
+/// fully transparent so it inherits the previous frame's content under
+/// `DisposalMethod::Keep`.
+pub(crate) fn extract_region(curr: &[u8], prev: &[u8], width: usize, region: &ChangedRegion) -> Vec<u8> {
+    let mut out = vec![0u8; region.width * region.height * 4];
+    for ry in 0..region.height {
+        let y = region.top + ry;
+        for rx in 0..region.width {
+            let x = region.left + rx;
+            let src = (y * width + x) * 4;
+            let dst = (ry * region.width + rx) * 4;
+            if curr[src..src + 4] == prev[src..src + 4] {
+                out[dst + 3] = 0;
+            } else {
+                out[dst..dst + 4].copy_from_slice(&curr[src..src + 4]);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_frames_have_no_changed_bounds() {
+        let frame = vec![1, 2, 3, 255, 4, 5, 6, 255];
+        assert!(changed_bounds(&frame, &frame, 2, 1).is_none());
+    }
+
+    #[test]
+    fn changed_bounds_is_tight_around_a_single_pixel() {
+        let (width, height) = (3, 3);
+        let prev = vec![0u8; width * height * 4];
+        let mut curr = prev.clone();
+        let idx = (width + 1) * 4;
+        curr[idx..idx + 4].copy_from_slice(&[255, 255, 255, 255]);
+
+        let region = changed_bounds(&prev, &curr, width, height).unwrap();
+
+        assert_eq!(
+            (region.left, region.top, region.width, region.height),
+            (1, 1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn extract_region_marks_unchanged_pixels_transparent() {
+        let width = 2;
+        let prev = vec![0u8; width * 4];
+        let mut curr = prev.clone();
+        curr[4..8].copy_from_slice(&[9, 9, 9, 255]);
+        let region = ChangedRegion {
+            left: 0,
+            top: 0,
+            width: 2,
+            height: 1,
+        };
+
+        let extracted = extract_region(&curr, &prev, width, &region);
+
+        assert_eq!(&extracted[0..4], &[0, 0, 0, 0]);
+        assert_eq!(&extracted[4..8], &[9, 9, 9, 255]);
+    }
+}
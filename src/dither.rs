@@ -0,0 +1,110 @@
+//! Serpentine Floyd–Steinberg error diffusion for palette mapping.
+//!
+//! Mapping each pixel to its nearest palette color independently posterizes
+//! gradients in photographic GIFs. This diffuses each pixel's quantization
+//! error to its not-yet-visited neighbors, alternating scan direction every
+//! row so the error doesn't accumulate a directional bias.
+
+use crate::quant::{Palette, TRANSPARENT_INDEX};
+
+/// Maps `rgba` to palette indices using serpentine Floyd–Steinberg
+/// dithering. Fully-transparent pixels are passed through untouched, with
+/// no error diffused through them, so caption edges stay crisp.
+pub(crate) fn floyd_steinberg(palette: &Palette, width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut indices = vec![TRANSPARENT_INDEX; width * height];
+    let mut error = vec![[0f32; 3]; width * height];
+
+    for y in 0..height {
+        let left_to_right = y % 2 == 0;
+        let dir: isize = if left_to_right { 1 } else { -1 };
+        let row: Box<dyn Iterator<Item = usize>> = if left_to_right {
+            Box::new(0..width)
+        } else {
+            Box::new((0..width).rev())
+        };
+
+        for x in row {
+            let i = y * width + x;
+            let p = i * 4;
+            if rgba[p + 3] == 0 {
+                continue;
+            }
+
+            let e = error[i];
+            let adjusted = [
+                (rgba[p] as f32 + e[0]).clamp(0.0, 255.0) as u8,
+                (rgba[p + 1] as f32 + e[1]).clamp(0.0, 255.0) as u8,
+                (rgba[p + 2] as f32 + e[2]).clamp(0.0, 255.0) as u8,
+            ];
+
+            let (index, color) = palette.nearest(&adjusted);
+            indices[i] = index;
+
+            let diff = [
+                adjusted[0] as f32 - color[0] as f32,
+                adjusted[1] as f32 - color[1] as f32,
+                adjusted[2] as f32 - color[2] as f32,
+            ];
+
+            diffuse(&mut error, width, height, x as isize + dir, y, diff, 7.0 / 16.0);
+            diffuse(&mut error, width, height, x as isize - dir, y + 1, diff, 3.0 / 16.0);
+            diffuse(&mut error, width, height, x as isize, y + 1, diff, 5.0 / 16.0);
+            diffuse(&mut error, width, height, x as isize + dir, y + 1, diff, 1.0 / 16.0);
+        }
+    }
+
+    indices
+}
+
+/// Accumulates `diff * weight` into the error buffer at `(x, y)`, if in bounds.
+fn diffuse(
+    error: &mut [[f32; 3]],
+    width: usize,
+    height: usize,
+    x: isize,
+    y: usize,
+    diff: [f32; 3],
+    weight: f32,
+) {
+    if x < 0 || x as usize >= width || y >= height {
+        return;
+    }
+    let i = y * width + x as usize;
+    for c in 0..3 {
+        error[i][c] += diff[c] * weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quant::Palette;
+
+    fn solid_frame(width: usize, height: usize, rgba: [u8; 4]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(width * height * 4);
+        for _ in 0..width * height {
+            frame.extend_from_slice(&rgba);
+        }
+        frame
+    }
+
+    #[test]
+    fn transparent_pixels_short_circuit() {
+        let palette = Palette::train(&[solid_frame(4, 4, [200, 100, 50, 255])], 10);
+        let rgba = solid_frame(2, 2, [0, 0, 0, 0]);
+
+        let indices = floyd_steinberg(&palette, 2, 2, &rgba);
+
+        assert_eq!(indices, vec![TRANSPARENT_INDEX; 4]);
+    }
+
+    #[test]
+    fn output_len_matches_pixel_count() {
+        let palette = Palette::train(&[solid_frame(4, 4, [10, 20, 30, 255])], 10);
+        let rgba = solid_frame(3, 2, [10, 20, 30, 255]);
+
+        let indices = floyd_steinberg(&palette, 3, 2, &rgba);
+
+        assert_eq!(indices.len(), 6);
+    }
+}
@@ -3,6 +3,16 @@ use std::io::Cursor;
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
 
+mod delta;
+mod dither;
+mod quant;
+mod resize;
+mod text;
+
+pub use resize::FilterType;
+
+use quant::Palette;
+
 /// Represents the dimensions of a GIF frame
 ///
 /// This struct is used to pass width and height information between Rust and JavaScript.
@@ -33,35 +43,56 @@ impl Dimensions {
 ///
 /// This enum provides specific error types for different failure scenarios
 /// in the GIF processing pipeline, making error handling more precise.
-#[derive(Debug)]
+/// `#[from]` conversions let call sites collapse `map_err` boilerplate into
+/// plain `?`.
+#[derive(Debug, thiserror::Error)]
 pub enum GifError {
     /// Error occurred during GIF decoding
-    DecodeError(String),
+    #[error("GIF decode error: {0}")]
+    DecodeError(#[from] gif::DecodingError),
     /// Error occurred during GIF encoding
-    EncodeError(String),
+    #[error("GIF encode error: {0}")]
+    EncodeError(#[from] gif::EncodingError),
     /// Error occurred during canvas operations
+    #[error("Canvas error: {0}")]
     CanvasError(String),
     /// Error due to invalid state or input
+    #[error("Invalid state: {0}")]
     InvalidState(String),
+    /// Error surfaced from a JavaScript API call
+    #[error("JS error: {0:?}")]
+    JsError(JsValue),
 }
 
-impl From<GifError> for JsValue {
-    fn from(error: GifError) -> Self {
-        JsValue::from_str(&error.to_string())
+impl From<JsValue> for GifError {
+    fn from(value: JsValue) -> Self {
+        GifError::JsError(value)
     }
 }
 
-impl std::fmt::Display for GifError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl GifError {
+    /// A machine-readable error kind so JS callers can branch on error type
+    /// rather than parsing the display message.
+    fn kind(&self) -> &'static str {
         match self {
-            GifError::DecodeError(e) => write!(f, "GIF decode error: {}", e),
-            GifError::EncodeError(e) => write!(f, "GIF encode error: {}", e),
-            GifError::CanvasError(e) => write!(f, "Canvas error: {}", e),
-            GifError::InvalidState(e) => write!(f, "Invalid state: {}", e),
+            GifError::DecodeError(_) => "decode_error",
+            GifError::EncodeError(_) => "encode_error",
+            GifError::CanvasError(_) => "canvas_error",
+            GifError::InvalidState(_) => "invalid_state",
+            GifError::JsError(_) => "js_error",
         }
     }
 }
 
+impl From<GifError> for JsValue {
+    fn from(error: GifError) -> Self {
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"message".into(), &error.to_string().into());
+        let _ = js_sys::Reflect::set(&obj, &"kind".into(), &error.kind().into());
+        obj.into()
+    }
+}
+
 /// Processes GIF images and adds captions
 ///
 /// This struct handles all GIF processing operations including:
@@ -82,6 +113,8 @@ pub struct GifProcessor {
     current_frame: usize,
     /// Delay time for each frame in centiseconds
     frame_delays: Vec<u16>,
+    /// Pure-Rust font used for DOM-free caption rasterization, if loaded
+    font: Option<text::TextRasterizer>,
 }
 
 #[wasm_bindgen]
@@ -98,7 +131,69 @@ impl GifProcessor {
             height: 0,
             current_frame: 0,
             frame_delays: Vec::new(),
+            font: None,
+        }
+    }
+
+    /// Load a font for DOM-free caption rasterization
+    ///
+    /// Parses a TTF/OTF font from raw bytes so `prepare_text_overlay_headless`
+    /// can rasterize captions without a `document`, letting it run in a Web
+    /// Worker or OffscreenCanvas context.
+    ///
+    /// # Arguments
+    /// * `font_bytes` - Raw bytes of a TTF/OTF font file
+    ///
+    /// # Returns
+    /// * `Result<(), JsValue>` - Ok if the font was loaded, Error if it failed
+    #[wasm_bindgen]
+    pub fn load_font(&mut self, font_bytes: &[u8]) -> Result<(), JsValue> {
+        let font = text::TextRasterizer::from_bytes(font_bytes)
+            .map_err(|e| GifError::InvalidState(format!("Failed to load font: {}", e)))?;
+        self.font = Some(font);
+        Ok(())
+    }
+
+    /// Prepare a text overlay for compositing without the DOM
+    ///
+    /// Rasterizes `text` with the font loaded via `load_font` instead of a
+    /// canvas, producing the same white-fill/black-outline RGBA overlay as
+    /// `prepare_text_overlay`. Unlike `prepare_text_overlay`, this never
+    /// touches `document` and can be called from a Web Worker.
+    ///
+    /// # Arguments
+    /// * `text` - The text to render
+    /// * `x` - X coordinate for text placement
+    /// * `y` - Y coordinate for text placement
+    /// * `font_size` - Size of the font in pixels
+    ///
+    /// # Returns
+    /// * `Result<Vec<u8>, JsValue>` - Ok with RGBA pixel data if successful, Error if it failed
+    #[wasm_bindgen]
+    pub fn prepare_text_overlay_headless(
+        &self,
+        text: &str,
+        x: f64,
+        y: f64,
+        font_size: f64,
+    ) -> Result<Vec<u8>, JsValue> {
+        if text.is_empty() {
+            return Ok(vec![0; self.width * self.height * 4]);
         }
+
+        let rasterizer = self
+            .font
+            .as_ref()
+            .ok_or_else(|| GifError::InvalidState("No font loaded; call load_font first".into()))?;
+
+        Ok(rasterizer.rasterize(
+            text,
+            x as f32,
+            y as f32,
+            font_size as f32,
+            self.width,
+            self.height,
+        ))
     }
 
     /// Process a GIF file and store its frames
@@ -110,20 +205,18 @@ impl GifProcessor {
     /// * `gif_data` - Raw bytes of the GIF file
     ///
     /// # Returns
-    /// * `Result<(), JsValue>` - Ok if processing succeeded, Error if it failed
+    /// * `Result<(), GifError>` - Ok if processing succeeded, Error if it failed
     #[wasm_bindgen]
-    pub fn process_gif(&mut self, gif_data: &[u8]) -> Result<(), JsValue> {
+    pub fn process_gif(&mut self, gif_data: &[u8]) -> Result<(), GifError> {
         if gif_data.is_empty() {
-            return Err(GifError::InvalidState("Empty GIF data provided".into()).into());
+            return Err(GifError::InvalidState("Empty GIF data provided".into()));
         }
 
         let cursor = Cursor::new(gif_data);
         let mut decoder = gif::DecodeOptions::new();
         decoder.set_color_output(gif::ColorOutput::RGBA);
 
-        let mut decoder = decoder
-            .read_info(cursor)
-            .map_err(|e| GifError::DecodeError(e.to_string()))?;
+        let mut decoder = decoder.read_info(cursor)?;
 
         self.width = decoder.width() as usize;
         self.height = decoder.height() as usize;
@@ -135,10 +228,7 @@ impl GifProcessor {
         let mut canvas = vec![0u8; self.width * self.height * 4];
         let mut previous_canvas = canvas.clone();
 
-        while let Some(frame) = decoder
-            .read_next_frame()
-            .map_err(|e| GifError::DecodeError(e.to_string()))?
-        {
+        while let Some(frame) = decoder.read_next_frame()? {
             let frame_width = frame.width as usize;
             let frame_height = frame.height as usize;
             let frame_top = frame.top as usize;
@@ -176,7 +266,7 @@ impl GifProcessor {
         }
 
         if self.frames.is_empty() {
-            return Err(GifError::InvalidState("No frames found in GIF".into()).into());
+            return Err(GifError::InvalidState("No frames found in GIF".into()));
         }
 
         Ok(())
@@ -262,6 +352,38 @@ impl GifProcessor {
         Ok(())
     }
 
+    /// Rescale all stored frames to a new size
+    ///
+    /// Resamples every stored frame (and updates `width`/`height`) before
+    /// captioning or export, so callers can normalize odd-sized source GIFs
+    /// or downscale huge ones to cap memory before per-frame storage grows
+    /// further. Alpha is premultiplied before filtering and un-premultiplied
+    /// after, so transparent edges don't darken.
+    ///
+    /// # Arguments
+    /// * `width` - Target width in pixels
+    /// * `height` - Target height in pixels
+    /// * `filter` - Resampling filter to use
+    ///
+    /// # Returns
+    /// * `Result<(), JsValue>` - Ok if resizing succeeded, Error if it failed
+    #[wasm_bindgen]
+    pub fn resize(&mut self, width: usize, height: usize, filter: FilterType) -> Result<(), JsValue> {
+        if width == 0 || height == 0 {
+            return Err(GifError::InvalidState("Resize target must be non-zero".into()).into());
+        }
+
+        self.frames = self
+            .frames
+            .iter()
+            .map(|frame| resize::resize_rgba(frame, self.width, self.height, width, height, filter))
+            .collect();
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+
     /// Get the dimensions of the GIF
     ///
     /// Returns the width and height of the GIF in pixels.
@@ -348,38 +470,83 @@ impl GifProcessor {
     ///
     /// Creates a new GIF with the text overlay composited onto each frame.
     /// The resulting GIF maintains the original timing and dimensions.
+    /// Every frame is mapped to indices of a single shared 256-color palette
+    /// (trained across all composited frames), so the animation has no
+    /// per-frame palette flicker.
     ///
     /// # Arguments
     /// * `text_data` - RGBA pixel data of the text overlay
+    /// * `quality` - NeuQuant sampling factor; `1` is highest quality/slowest,
+    ///   higher values sample more sparsely for faster palette training
+    /// * `dither` - Whether to apply serpentine Floyd–Steinberg dithering
+    ///   when mapping pixels to the shared palette
     ///
     /// # Returns
-    /// * `Result<Vec<u8>, JsValue>` - Ok with the new GIF data if successful, Error if it failed
+    /// * `Result<Vec<u8>, GifError>` - Ok with the new GIF data if successful, Error if it failed
     #[wasm_bindgen]
-    pub fn process_all_frames_with_text_data(&self, text_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    pub fn process_all_frames_with_text_data(
+        &self,
+        text_data: &[u8],
+        quality: u8,
+        dither: bool,
+    ) -> Result<Vec<u8>, GifError> {
         if self.frames.is_empty() {
-            return Err(GifError::InvalidState("No frames to process".into()).into());
+            return Err(GifError::InvalidState("No frames to process".into()));
         }
 
-        let mut output = Vec::with_capacity(self.frames.len() * self.width * self.height);
-        {
-            let mut encoder = Encoder::new(&mut output, self.width as u16, self.height as u16, &[])
-                .map_err(|e| GifError::EncodeError(e.to_string()))?;
+        let composited: Vec<Vec<u8>> = self
+            .frames
+            .iter()
+            .map(|frame_data| self.composite_text_overlay(frame_data, text_data))
+            .collect();
 
-            encoder
-                .set_repeat(Repeat::Infinite)
-                .map_err(|e| GifError::EncodeError(e.to_string()))?;
+        let palette = Palette::train(&composited, quality);
+        let color_table = palette.color_table();
 
-            for (i, frame_data) in self.frames.iter().enumerate() {
-                let mut modified_data = self.composite_text_overlay(frame_data, text_data);
-
-                let mut frame =
-                    Frame::from_rgba(self.width as u16, self.height as u16, &mut modified_data);
+        let mut output = Vec::with_capacity(self.frames.len() * self.width * self.height);
+        {
+            let mut encoder =
+                Encoder::new(&mut output, self.width as u16, self.height as u16, &color_table)?;
+
+            encoder.set_repeat(Repeat::Infinite)?;
+
+            for (i, rgba) in composited.iter().enumerate() {
+                // composited[i] is already a fully-resolved full-canvas
+                // buffer (process_gif bakes in the source disposal before
+                // storing it), so diffing it against composited[i - 1] is
+                // valid regardless of the source frame's own disposal byte.
+                let (left, top, region_width, region_height, region_rgba) = if i > 0 {
+                    let prev = &composited[i - 1];
+                    match delta::changed_bounds(prev, rgba, self.width, self.height) {
+                        Some(region) => {
+                            let extracted = delta::extract_region(rgba, prev, self.width, &region);
+                            (region.left, region.top, region.width, region.height, extracted)
+                        }
+                        None => (0, 0, 1, 1, vec![0u8; 4]),
+                    }
+                } else {
+                    (0, 0, self.width, self.height, rgba.clone())
+                };
+
+                let indices = if dither {
+                    dither::floyd_steinberg(&palette, region_width, region_height, &region_rgba)
+                } else {
+                    palette.map_frame(&region_rgba)
+                };
+
+                let mut frame = Frame::from_indexed_pixels(
+                    region_width as u16,
+                    region_height as u16,
+                    indices,
+                    None,
+                );
+                frame.left = left as u16;
+                frame.top = top as u16;
                 frame.delay = self.frame_delays[i];
                 frame.dispose = gif::DisposalMethod::Keep;
+                frame.transparent = Some(quant::TRANSPARENT_INDEX);
 
-                encoder
-                    .write_frame(&frame)
-                    .map_err(|e| GifError::EncodeError(e.to_string()))?;
+                encoder.write_frame(&frame)?;
             }
         }
         Ok(output)
@@ -0,0 +1,141 @@
+//! Shared-palette quantization for GIF export.
+//!
+//! Exported GIFs previously quantized each frame independently via the
+//! `gif` crate's built-in palette builder, which causes color banding and
+//! palette flicker between frames. This module trains a single NeuQuant
+//! network across every composited frame so the whole animation shares one
+//! high-quality 256-color palette, the way gifski and similar tools do.
+
+use color_quant::NeuQuant;
+
+/// Number of entries in the exported global color table.
+const PALETTE_SIZE: usize = 256;
+
+/// Palette index reserved for fully-transparent pixels.
+pub(crate) const TRANSPARENT_INDEX: u8 = (PALETTE_SIZE - 1) as u8;
+
+/// A 256-color palette trained once across every composited frame.
+pub(crate) struct Palette {
+    quant: NeuQuant,
+    /// RGB triples for indices `0..PALETTE_SIZE - 1`, cached for `nearest`.
+    table: Vec<u8>,
+}
+
+impl Palette {
+    /// Trains a NeuQuant network over the pixels of every frame.
+    ///
+    /// `quality` mirrors `color_quant::NeuQuant`'s sampling factor: `1`
+    /// samples every pixel (best quality, slowest), higher values sample
+    /// more sparsely for faster training. Fully-transparent pixels are
+    /// excluded from training since `map_frame`/`floyd_steinberg` always
+    /// route them to `TRANSPARENT_INDEX` instead of a trained neuron, so
+    /// including them would waste palette capacity on a color no pixel can
+    /// actually select.
+    pub(crate) fn train(frames: &[Vec<u8>], quality: u8) -> Palette {
+        let samplefac = quality.max(1) as i32;
+        let mut pixels = Vec::with_capacity(frames.iter().map(Vec::len).sum());
+        for frame in frames {
+            for pixel in frame.chunks_exact(4) {
+                if pixel[3] != 0 {
+                    pixels.extend_from_slice(pixel);
+                }
+            }
+        }
+        if pixels.is_empty() {
+            pixels.extend_from_slice(&[0, 0, 0, 255]);
+        }
+
+        let quant = NeuQuant::new(samplefac, PALETTE_SIZE - 1, &pixels);
+        let mut table = quant.color_map_rgb();
+        table.truncate((PALETTE_SIZE - 1) * 3);
+
+        Palette { quant, table }
+    }
+
+    /// Returns the palette index and RGB color nearest to `rgb`.
+    pub(crate) fn nearest(&self, rgb: &[u8; 3]) -> (u8, [u8; 3]) {
+        let index = self.quant.index_of(&[rgb[0], rgb[1], rgb[2], 0xff]) as u8;
+        let t = index as usize * 3;
+        (index, [self.table[t], self.table[t + 1], self.table[t + 2]])
+    }
+
+    /// Returns the RGB global color table, with the last slot reserved for
+    /// the transparent index.
+    pub(crate) fn color_table(&self) -> Vec<u8> {
+        let mut table = self.quant.color_map_rgb();
+        table.truncate((PALETTE_SIZE - 1) * 3);
+        table.extend_from_slice(&[0, 0, 0]);
+        table
+    }
+
+    /// Maps one composited RGBA frame to palette indices, reserving
+    /// [`TRANSPARENT_INDEX`] for pixels with zero alpha.
+    pub(crate) fn map_frame(&self, rgba: &[u8]) -> Vec<u8> {
+        rgba.chunks_exact(4)
+            .map(|pixel| {
+                if pixel[3] == 0 {
+                    TRANSPARENT_INDEX
+                } else {
+                    self.quant.index_of(pixel) as u8
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_frame(width: usize, height: usize, rgba: [u8; 4]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(width * height * 4);
+        for _ in 0..width * height {
+            frame.extend_from_slice(&rgba);
+        }
+        frame
+    }
+
+    #[test]
+    fn color_table_has_reserved_transparent_slot() {
+        let frames = vec![solid_frame(4, 4, [255, 0, 0, 255])];
+        let palette = Palette::train(&frames, 10);
+        let table = palette.color_table();
+
+        assert_eq!(table.len(), PALETTE_SIZE * 3);
+        let last = (PALETTE_SIZE - 1) * 3;
+        assert_eq!(&table[last..last + 3], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn map_frame_reserves_transparent_index_for_zero_alpha() {
+        let frames = vec![solid_frame(4, 4, [255, 0, 0, 255])];
+        let palette = Palette::train(&frames, 10);
+
+        let transparent_pixel = solid_frame(1, 1, [10, 20, 30, 0]);
+        let indices = palette.map_frame(&transparent_pixel);
+
+        assert_eq!(indices, vec![TRANSPARENT_INDEX]);
+    }
+
+    #[test]
+    fn train_ignores_fully_transparent_pixels() {
+        // A frame that's mostly the "reserved" transparent color (0,0,0,0)
+        // with one opaque red pixel: if transparent pixels leaked into
+        // training, the palette would be dominated by near-black entries
+        // instead of clustering around the one real opaque color.
+        let mut frame = solid_frame(8, 8, [0, 0, 0, 0]);
+        frame[0..4].copy_from_slice(&[255, 0, 0, 255]);
+
+        let palette = Palette::train(&[frame], 1);
+        let (_, color) = palette.nearest(&[255, 0, 0]);
+
+        assert!(color[0] > 200, "expected red to dominate, got {:?}", color);
+    }
+
+    #[test]
+    fn train_on_all_transparent_frame_does_not_panic() {
+        let frames = vec![solid_frame(4, 4, [0, 0, 0, 0])];
+        let palette = Palette::train(&frames, 10);
+        assert_eq!(palette.color_table().len(), PALETTE_SIZE * 3);
+    }
+}
@@ -0,0 +1,211 @@
+//! Separable image resampling for normalizing source GIF dimensions.
+//!
+//! Captions are placed in source-pixel coordinates, so odd-sized source
+//! GIFs can't be normalized to a target render/export size without
+//! resampling every stored frame first. Filters are implemented as
+//! separable horizontal + vertical passes, premultiplying alpha before
+//! filtering and un-premultiplying after so transparent edges don't darken.
+
+use wasm_bindgen::prelude::*;
+
+/// Resampling filter used by [`resize`](crate::GifProcessor::resize).
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    /// Nearest-neighbor sampling. Fast, blocky.
+    Nearest,
+    /// Bilinear (triangle) filter. Good general-purpose default.
+    Triangle,
+    /// Lanczos windowed-sinc filter with a = 3. Sharpest, slowest.
+    Lanczos3,
+}
+
+impl FilterType {
+    /// Filter support radius, in source pixels, before scaling.
+    fn support(self) -> f32 {
+        match self {
+            FilterType::Nearest => 0.5,
+            FilterType::Triangle => 1.0,
+            FilterType::Lanczos3 => 3.0,
+        }
+    }
+
+    /// Filter weight at distance `x` (in source pixels).
+    fn weight(self, x: f32) -> f32 {
+        match self {
+            FilterType::Nearest => {
+                if x.abs() < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            FilterType::Triangle => (1.0 - x.abs()).max(0.0),
+            FilterType::Lanczos3 => {
+                if x.abs() >= 3.0 {
+                    0.0
+                } else {
+                    sinc(x) * sinc(x / 3.0)
+                }
+            }
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let px = std::f32::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Resamples premultiplied-alpha-safe RGBA `src` from `src_w x src_h` to
+/// `dst_w x dst_h` using separable horizontal then vertical passes.
+pub(crate) fn resize_rgba(
+    src: &[u8],
+    src_w: usize,
+    src_h: usize,
+    dst_w: usize,
+    dst_h: usize,
+    filter: FilterType,
+) -> Vec<u8> {
+    let premultiplied = premultiply(src);
+    let horizontal = resample_axis(&premultiplied, src_w, src_h, dst_w, true, filter);
+    let resampled = resample_axis(&horizontal, dst_w, src_h, dst_h, false, filter);
+    unpremultiply(&resampled)
+}
+
+fn premultiply(rgba: &[u8]) -> Vec<f32> {
+    rgba.chunks_exact(4)
+        .flat_map(|p| {
+            let a = p[3] as f32 / 255.0;
+            [p[0] as f32 * a, p[1] as f32 * a, p[2] as f32 * a, p[3] as f32]
+        })
+        .collect()
+}
+
+fn unpremultiply(rgba: &[f32]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .flat_map(|p| {
+            let a = p[3] / 255.0;
+            if a <= 0.0 {
+                [0u8, 0, 0, 0]
+            } else {
+                [
+                    (p[0] / a).clamp(0.0, 255.0) as u8,
+                    (p[1] / a).clamp(0.0, 255.0) as u8,
+                    (p[2] / a).clamp(0.0, 255.0) as u8,
+                    p[3].clamp(0.0, 255.0) as u8,
+                ]
+            }
+        })
+        .collect()
+}
+
+/// Resamples one axis (horizontal when `horizontal` is true, else vertical)
+/// of a premultiplied RGBA buffer from `src_len` to `dst_len` samples.
+fn resample_axis(
+    src: &[f32],
+    src_w: usize,
+    src_h: usize,
+    dst_len: usize,
+    horizontal: bool,
+    filter: FilterType,
+) -> Vec<f32> {
+    let src_len = if horizontal { src_w } else { src_h };
+    let other_len = if horizontal { src_h } else { src_w };
+    let out_w = if horizontal { dst_len } else { src_w };
+
+    let scale = src_len as f32 / dst_len as f32;
+    let filter_scale = if filter == FilterType::Nearest {
+        1.0
+    } else {
+        scale.max(1.0)
+    };
+    let support = filter.support() * filter_scale;
+
+    let mut out = vec![0f32; out_w * other_len * 4];
+
+    for dst_i in 0..dst_len {
+        let center = (dst_i as f32 + 0.5) * scale;
+        let start = (center - support).floor().max(0.0) as usize;
+        let end = ((center + support).ceil() as isize).min(src_len as isize - 1).max(0) as usize;
+
+        let mut weights = Vec::new();
+        let mut total = 0f32;
+        for s in start..=end {
+            let w = filter.weight((s as f32 + 0.5 - center) / filter_scale);
+            if w != 0.0 {
+                weights.push((s, w));
+                total += w;
+            }
+        }
+        if total == 0.0 {
+            weights.push((center.clamp(0.0, (src_len - 1) as f32) as usize, 1.0));
+            total = 1.0;
+        }
+
+        for other in 0..other_len {
+            let mut sum = [0f32; 4];
+            for &(s, w) in &weights {
+                let idx = if horizontal {
+                    other * src_w + s
+                } else {
+                    s * src_w + other
+                };
+                let p = idx * 4;
+                for c in 0..4 {
+                    sum[c] += src[p + c] * w;
+                }
+            }
+
+            let out_idx = if horizontal {
+                other * out_w + dst_i
+            } else {
+                dst_i * out_w + other
+            };
+            let op = out_idx * 4;
+            for c in 0..4 {
+                out[op + c] = sum[c] / total;
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_produces_target_pixel_count() {
+        let src = vec![
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 0, 255,
+        ];
+        let out = resize_rgba(&src, 2, 2, 1, 1, FilterType::Nearest);
+        assert_eq!(out.len(), 4);
+
+        let out = resize_rgba(&src, 2, 2, 4, 4, FilterType::Lanczos3);
+        assert_eq!(out.len(), 4 * 4 * 4);
+    }
+
+    #[test]
+    fn resize_keeps_opaque_uniform_pixels_opaque_and_in_color() {
+        let mut src = Vec::new();
+        for _ in 0..4 {
+            src.extend_from_slice(&[200, 100, 50, 255]);
+        }
+
+        let out = resize_rgba(&src, 2, 2, 4, 4, FilterType::Triangle);
+
+        for pixel in out.chunks_exact(4) {
+            assert_eq!(pixel[3], 255);
+            assert!((pixel[0] as i32 - 200).abs() <= 1);
+            assert!((pixel[1] as i32 - 100).abs() <= 1);
+            assert!((pixel[2] as i32 - 50).abs() <= 1);
+        }
+    }
+}
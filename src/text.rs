@@ -0,0 +1,165 @@
+//! DOM-free caption rasterization.
+//!
+//! `prepare_text_overlay` hard-binds the export pipeline to the main thread
+//! by calling into `web_sys::window().document()`, which is unavailable in a
+//! Web Worker / OffscreenCanvas context. This lays out and rasterizes glyphs
+//! with a pure-Rust font renderer so callers can build the same RGBA overlay
+//! buffer entirely off-main-thread.
+
+use ab_glyph::{Font, FontArc, GlyphId, Point, PxScale, ScaleFont};
+
+/// Outline stroke width, in pixels, matching `setup_text_style`'s
+/// `context.set_line_width(3.0)` so the two rasterization paths agree.
+const STROKE_WIDTH_PX: f32 = 3.0;
+
+/// Rasterizes captions into RGBA overlay buffers without touching the DOM.
+pub(crate) struct TextRasterizer {
+    font: FontArc,
+}
+
+impl TextRasterizer {
+    /// Loads a TTF/OTF font from raw bytes.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<TextRasterizer, String> {
+        let font = FontArc::try_from_vec(bytes.to_vec()).map_err(|e| e.to_string())?;
+        Ok(TextRasterizer { font })
+    }
+
+    /// Lays out `text` at `(x, y)` and fills a `width * height * 4` RGBA
+    /// buffer with the same white-fill/black-outline styling used by the
+    /// canvas text path, dilating the outline to match its stroke width
+    /// (see [`STROKE_WIDTH_PX`]).
+    pub(crate) fn rasterize(
+        &self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        width: usize,
+        height: usize,
+    ) -> Vec<u8> {
+        let scale = PxScale::from(font_size);
+        let scaled_font = self.font.as_scaled(scale);
+
+        let mut coverage = vec![0f32; width * height];
+        let mut cursor_x = x;
+        let mut previous: Option<GlyphId> = None;
+
+        for ch in text.chars() {
+            let glyph_id = self.font.glyph_id(ch);
+            if let Some(prev) = previous {
+                cursor_x += scaled_font.kern(prev, glyph_id);
+            }
+
+            let glyph = glyph_id.with_scale_and_position(scale, Point { x: cursor_x, y });
+            if let Some(outlined) = self.font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, c| {
+                    let px = bounds.min.x as i32 + gx as i32;
+                    let py = bounds.min.y as i32 + gy as i32;
+                    if px >= 0 && py >= 0 && (px as usize) < width && (py as usize) < height {
+                        let i = py as usize * width + px as usize;
+                        coverage[i] = coverage[i].max(c);
+                    }
+                });
+            }
+
+            cursor_x += scaled_font.h_advance(glyph_id);
+            previous = Some(glyph_id);
+        }
+
+        fill_from_coverage(&coverage, width, height)
+    }
+}
+
+/// Dilates glyph coverage outward by half of [`STROKE_WIDTH_PX`] to
+/// approximate a stroked outline of that width, then fills white glyph
+/// interiors over a black outline like `setup_text_style`'s fill/stroke
+/// pair.
+fn fill_from_coverage(coverage: &[f32], width: usize, height: usize) -> Vec<u8> {
+    let radius = (STROKE_WIDTH_PX / 2.0).round().max(1.0) as isize;
+
+    let mut dilated = vec![0f32; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let mut max = 0f32;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height {
+                        max = max.max(coverage[ny as usize * width + nx as usize]);
+                    }
+                }
+            }
+            dilated[y * width + x] = max;
+        }
+    }
+
+    let mut out = vec![0u8; width * height * 4];
+    for i in 0..width * height {
+        let outline = dilated[i];
+        if outline <= 0.0 {
+            continue;
+        }
+
+        let p = i * 4;
+        if coverage[i] > 0.0 {
+            out[p..p + 3].copy_from_slice(&[255, 255, 255]);
+        } else {
+            out[p..p + 3].copy_from_slice(&[0, 0, 0]);
+        }
+        out[p + 3] = (outline * 255.0) as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(out: &[u8], width: usize, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * width + x) * 4;
+        [out[i], out[i + 1], out[i + 2], out[i + 3]]
+    }
+
+    #[test]
+    fn dilation_extends_roughly_half_the_stroke_width_beyond_coverage() {
+        let (width, height) = (7, 7);
+        let (cx, cy) = (3, 3);
+        let mut coverage = vec![0f32; width * height];
+        coverage[cy * width + cx] = 1.0;
+
+        let out = fill_from_coverage(&coverage, width, height);
+        let radius = (STROKE_WIDTH_PX / 2.0).round() as usize;
+
+        // Just inside the expected dilation radius: outline alpha present.
+        assert!(pixel(&out, width, cx + radius, cy)[3] > 0);
+        // One pixel beyond the expected radius: no outline bled out that far.
+        assert_eq!(pixel(&out, width, cx + radius + 1, cy)[3], 0);
+    }
+
+    #[test]
+    fn covered_pixels_are_white_and_halo_only_pixels_are_black() {
+        let (width, height) = (5, 5);
+        let (cx, cy) = (2, 2);
+        let mut coverage = vec![0f32; width * height];
+        coverage[cy * width + cx] = 1.0;
+
+        let out = fill_from_coverage(&coverage, width, height);
+
+        assert_eq!(pixel(&out, width, cx, cy), [255, 255, 255, 255]);
+
+        let halo = pixel(&out, width, cx + 1, cy);
+        assert_eq!(&halo[0..3], &[0, 0, 0]);
+        assert!(halo[3] > 0);
+    }
+
+    #[test]
+    fn no_coverage_produces_fully_transparent_buffer() {
+        let (width, height) = (4, 4);
+        let coverage = vec![0f32; width * height];
+
+        let out = fill_from_coverage(&coverage, width, height);
+
+        assert!(out.iter().all(|&b| b == 0));
+    }
+}